@@ -0,0 +1,310 @@
+//! 内置本地转发代理。
+//!
+//! `launch_with_proxy` 默认把代理环境变量直接指向用户配置的上游地址，目标
+//! 程序发出的请求因此完全不可见。这个模块在本地起一个 HTTP 代理协议的监听
+//! 端口，代为把流量转发到真正的上游（HTTP 或 SOCKS4/5），并把每条连接的
+//! 基本信息记到一个环形缓冲区里，供 GUI 的「流量监控」面板展示——本质上是
+//! 一个只为这个启动器服务、按需开启的抓包代理。
+
+use crate::{ProxyEndpoint, ProxyProtocol};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 流量日志最多保留的连接数，超出后丢弃最旧的。
+const MAX_LOG_ENTRIES: usize = 200;
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+
+/// 一条连接的流量记录，供「流量监控」面板渲染。
+#[derive(Clone)]
+pub struct ConnEntry {
+    pub started_at: Instant,
+    pub client_addr: String,
+    pub target: String,
+    pub bytes_up: u64,
+    pub bytes_down: u64,
+    pub duration: Duration,
+}
+
+type ConnLog = Arc<Mutex<VecDeque<ConnEntry>>>;
+
+/// 本地转发代理的句柄。监听线程随进程退出而结束，这里不做优雅关闭——
+/// 和 `HealthMonitor` 一样，单进程 GUI 工具没必要为此引入取消机制。
+pub struct LocalProxy {
+    pub port: u16,
+    log: ConnLog,
+}
+
+impl LocalProxy {
+    /// 在 `127.0.0.1:port` 上启动监听线程，所有连接转发到 `upstream`。
+    pub fn spawn(port: u16, upstream: ProxyEndpoint) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let log: ConnLog = Arc::new(Mutex::new(VecDeque::new()));
+        let thread_log = log.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(client) = stream else { continue };
+                let upstream = upstream.clone();
+                let log = thread_log.clone();
+                std::thread::spawn(move || handle_connection(client, &upstream, &log));
+            }
+        });
+
+        Ok(Self { port, log })
+    }
+
+    /// 按从新到旧的顺序返回当前日志快照。
+    pub fn recent_entries(&self) -> Vec<ConnEntry> {
+        self.log.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
+
+fn handle_connection(client: TcpStream, upstream: &ProxyEndpoint, log: &ConnLog) {
+    let client_addr = client
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|_| "未知".to_string());
+    let started_at = Instant::now();
+
+    let Some(header) = read_header(&client) else {
+        return;
+    };
+    let Some((method, raw_target)) = parse_request_line(&header) else {
+        return;
+    };
+    let is_connect = method.eq_ignore_ascii_case("CONNECT");
+    let target = if is_connect {
+        parse_host_port(&raw_target)
+    } else {
+        parse_absolute_uri(&raw_target)
+    };
+    let Some((host, port)) = target else {
+        return;
+    };
+
+    let Ok(mut upstream_conn) = dial_upstream(upstream, is_connect, &header, &host, port) else {
+        let _ = (&client).write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n");
+        push_entry(log, started_at, client_addr, format!("{host}:{port} (连接上游失败)"), 0, 0);
+        return;
+    };
+
+    if is_connect && (&client).write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").is_err() {
+        return;
+    }
+
+    let (bytes_up, bytes_down) = copy_bidirectional(&client, &mut upstream_conn);
+    let preamble = if is_connect { 0 } else { header.len() as u64 };
+
+    push_entry(log, started_at, client_addr, format!("{host}:{port}"), bytes_up + preamble, bytes_down);
+}
+
+/// 把一条连接记录追加到流量日志，超出上限时丢弃最旧的。
+fn push_entry(log: &ConnLog, started_at: Instant, client_addr: String, target: String, bytes_up: u64, bytes_down: u64) {
+    let mut guard = log.lock().unwrap();
+    guard.push_back(ConnEntry {
+        started_at,
+        client_addr,
+        target,
+        bytes_up,
+        bytes_down,
+        duration: started_at.elapsed(),
+    });
+    while guard.len() > MAX_LOG_ENTRIES {
+        guard.pop_front();
+    }
+}
+
+/// 从客户端连接里读出请求行和请求头（直到空行），原样返回以便在需要时
+/// 转发给上游（比如绝对 URI 形式的普通 HTTP 请求）。
+fn read_header(mut stream: &TcpStream) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        match stream.read(&mut byte) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(_) => return None,
+        }
+        buf.push(byte[0]);
+        if buf.len() >= 4 && &buf[buf.len() - 4..] == b"\r\n\r\n" {
+            return Some(buf);
+        }
+        if buf.len() > MAX_HEADER_BYTES {
+            return None;
+        }
+    }
+}
+
+fn parse_request_line(header: &[u8]) -> Option<(String, String)> {
+    let text = std::str::from_utf8(header).ok()?;
+    let line = text.lines().next()?;
+    let mut parts = line.split_whitespace();
+    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+}
+
+/// 解析 `CONNECT host:port` 里的目标地址。
+fn parse_host_port(target: &str) -> Option<(String, u16)> {
+    let (host, port) = target.rsplit_once(':')?;
+    let port = port.parse().ok()?;
+    Some((host.to_string(), port))
+}
+
+/// 解析普通 HTTP 代理请求里的绝对 URI（如 `http://host:port/path`）。
+fn parse_absolute_uri(target: &str) -> Option<(String, u16)> {
+    let is_https = target.starts_with("https://");
+    let without_scheme = target.splitn(2, "://").nth(1)?;
+    let authority = without_scheme.splitn(2, '/').next()?;
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), port.parse().ok()?)),
+        None => Some((authority.to_string(), if is_https { 443 } else { 80 })),
+    }
+}
+
+/// 连接配置的上游端点，并按其协议完成必要的握手，使之后的字节可以直接
+/// 双向透传到 `host:port`。
+fn dial_upstream(
+    endpoint: &ProxyEndpoint,
+    is_connect: bool,
+    raw_header: &[u8],
+    host: &str,
+    port: u16,
+) -> io::Result<TcpStream> {
+    let addr = format!("{}:{}", endpoint.ip.trim(), endpoint.port.trim());
+    let mut conn = TcpStream::connect(addr)?;
+
+    match endpoint.protocol {
+        ProxyProtocol::Http => {
+            conn.write_all(raw_header)?;
+            if is_connect && !read_http_connect_ok(&mut conn) {
+                return Err(io::Error::other("上游 HTTP 代理拒绝了 CONNECT"));
+            }
+        }
+        ProxyProtocol::Socks5 => {
+            socks5_connect(&mut conn, host, port)?;
+            if !is_connect {
+                conn.write_all(raw_header)?;
+            }
+        }
+        ProxyProtocol::Socks4 => {
+            socks4_connect(&mut conn, host, port)?;
+            if !is_connect {
+                conn.write_all(raw_header)?;
+            }
+        }
+    }
+
+    Ok(conn)
+}
+
+fn read_http_connect_ok(conn: &mut TcpStream) -> bool {
+    let mut buf = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        match conn.read(&mut byte) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+        buf.push(byte[0]);
+        if buf.len() >= 4 && &buf[buf.len() - 4..] == b"\r\n\r\n" {
+            break;
+        }
+        if buf.len() > MAX_HEADER_BYTES {
+            return false;
+        }
+    }
+    std::str::from_utf8(&buf)
+        .map(|s| s.lines().next().unwrap_or("").contains(" 200"))
+        .unwrap_or(false)
+}
+
+/// 无认证的 SOCKS5 客户端握手，请求上游把连接 CONNECT 到 `host:port`。
+fn socks5_connect(conn: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    conn.write_all(&[0x05, 0x01, 0x00])?;
+    let mut greeting_reply = [0u8; 2];
+    conn.read_exact(&mut greeting_reply)?;
+    if greeting_reply != [0x05, 0x00] {
+        return Err(io::Error::other("SOCKS5 握手失败（上游不支持无认证模式）"));
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    conn.write_all(&request)?;
+
+    let mut reply_head = [0u8; 4];
+    conn.read_exact(&mut reply_head)?;
+    if reply_head[1] != 0x00 {
+        return Err(io::Error::other(format!("SOCKS5 CONNECT 被拒绝（code={}）", reply_head[1])));
+    }
+
+    // 跳过回应里携带的绑定地址，地址长度取决于地址类型。
+    let skip_len = match reply_head[3] {
+        0x01 => 4,
+        0x03 => {
+            let mut len = [0u8; 1];
+            conn.read_exact(&mut len)?;
+            len[0] as usize
+        }
+        0x04 => 16,
+        _ => return Err(io::Error::other("SOCKS5 返回了未知的地址类型")),
+    };
+    let mut skip = vec![0u8; skip_len + 2];
+    conn.read_exact(&mut skip)?;
+    Ok(())
+}
+
+/// SOCKS4 客户端握手，只支持 IPv4 目标（协议本身不支持域名解析）。
+fn socks4_connect(conn: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    let ipv4 = (host, port)
+        .to_socket_addrs()?
+        .find_map(|addr| match addr {
+            SocketAddr::V4(v4) => Some(v4.ip().octets()),
+            SocketAddr::V6(_) => None,
+        })
+        .ok_or_else(|| io::Error::other("SOCKS4 只支持 IPv4 目标地址"))?;
+
+    let mut request = vec![0x04, 0x01];
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&ipv4);
+    request.push(0x00);
+    conn.write_all(&request)?;
+
+    let mut reply = [0u8; 8];
+    conn.read_exact(&mut reply)?;
+    if reply[1] != 0x5a {
+        return Err(io::Error::other(format!("SOCKS4 CONNECT 被拒绝（code={}）", reply[1])));
+    }
+    Ok(())
+}
+
+/// 在两个方向上同时透传字节，返回 (上行字节数, 下行字节数)。
+///
+/// 下行方向在当前线程完成；上行方向放到另一个线程里跑，任一方向结束后
+/// 把两个 socket 都 shutdown 掉，这样另一个方向的阻塞读也能尽快退出。
+fn copy_bidirectional(client: &TcpStream, upstream: &mut TcpStream) -> (u64, u64) {
+    let up_handle = match (client.try_clone(), upstream.try_clone()) {
+        (Ok(mut client_read), Ok(mut upstream_write)) => {
+            Some(std::thread::spawn(move || {
+                let bytes = io::copy(&mut client_read, &mut upstream_write).unwrap_or(0);
+                let _ = client_read.shutdown(Shutdown::Both);
+                let _ = upstream_write.shutdown(Shutdown::Both);
+                bytes
+            }))
+        }
+        _ => None,
+    };
+
+    let bytes_down = match client.try_clone() {
+        Ok(mut client_write) => io::copy(upstream, &mut client_write).unwrap_or(0),
+        Err(_) => 0,
+    };
+
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = upstream.shutdown(Shutdown::Both);
+
+    let bytes_up = up_handle.and_then(|h| h.join().ok()).unwrap_or(0);
+    (bytes_up, bytes_down)
+}
@@ -1,10 +1,22 @@
 use eframe::egui;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use sysinfo::System;
 
+/// 健康检查单次探测的超时，以及整个就绪等待的上限。
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_millis(300);
+const HEALTH_PROBE_INTERVAL: Duration = Duration::from_millis(300);
+const HEALTH_WAIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+mod local_proxy;
+#[cfg(target_os = "windows")]
+mod system_proxy;
+
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions::default();
     eframe::run_native(
@@ -81,17 +93,164 @@ impl ProxyProtocol {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-struct ProxyProfile {
-    name: String,
+/// 一个上游代理地址。`ProxyProfile` 持有一个有序列表，配合 `ProxyStrategy`
+/// 决定每次启动实际使用哪一个。
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct ProxyEndpoint {
     ip: String,
     port: String,
     protocol: ProxyProtocol,
 }
 
+impl ProxyEndpoint {
+    fn url(&self) -> Result<String, String> {
+        if self.ip.trim().is_empty() {
+            return Err("IP 地址不能为空".to_string());
+        }
+        let port = self
+            .port
+            .trim()
+            .parse::<u16>()
+            .map_err(|_| "端口号无效（1-65535）".to_string())?;
+        Ok(format!("{}://{}:{}", self.protocol.as_scheme(), self.ip.trim(), port))
+    }
+
+    fn socket_port(&self) -> Option<u16> {
+        self.port.trim().parse().ok()
+    }
+}
+
+/// 多个上游代理之间的选择策略。
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+enum ProxyStrategy {
+    /// 每次启动轮到下一个端点，轮转游标持久化在所属配置上。
+    RoundRobin,
+    /// 按顺序探测端点，使用第一个 TCP 可达的。
+    Failover,
+}
+
+impl Default for ProxyStrategy {
+    fn default() -> Self {
+        Self::RoundRobin
+    }
+}
+
+impl ProxyStrategy {
+    fn label(self) -> &'static str {
+        match self {
+            Self::RoundRobin => "轮询",
+            Self::Failover => "故障转移",
+        }
+    }
+}
+
+/// 在 `endpoints` 中按轮询游标选出下一个端点，返回它和游标的下一个值。
+fn select_round_robin(endpoints: &[ProxyEndpoint], cursor: usize) -> (ProxyEndpoint, usize) {
+    let idx = cursor % endpoints.len();
+    (endpoints[idx].clone(), cursor.wrapping_add(1))
+}
+
+/// 按顺序探测 `endpoints`，返回第一个 `probe` 判定可达的端点。
+fn select_failover<F: Fn(&str, u16) -> bool>(
+    endpoints: &[ProxyEndpoint],
+    probe: F,
+) -> Option<ProxyEndpoint> {
+    endpoints
+        .iter()
+        .find(|endpoint| endpoint.socket_port().is_some_and(|port| probe(endpoint.ip.trim(), port)))
+        .cloned()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "ProxyProfileOnDisk")]
+struct ProxyProfile {
+    name: String,
+    endpoints: Vec<ProxyEndpoint>,
+    #[serde(default)]
+    strategy: ProxyStrategy,
+    /// 轮询策略下一次要使用的下标，跨启动持久化到磁盘。
+    #[serde(default)]
+    next_index: usize,
+    #[serde(default)]
+    health_check_enabled: bool,
+    #[serde(default)]
+    health_check_host: String,
+    #[serde(default)]
+    health_check_port: String,
+    #[serde(default = "default_max_restarts")]
+    max_restarts: u32,
+    #[serde(default = "default_bypass")]
+    bypass: String,
+}
+
+/// 磁盘上的旧格式只有单个 ip/port/protocol，而不是 `endpoints` 列表。
+/// 通过这个中间结构体把旧格式读成一个单元素列表，保持向后兼容。
+#[derive(Deserialize)]
+struct ProxyProfileOnDisk {
+    name: String,
+    #[serde(default)]
+    endpoints: Vec<ProxyEndpoint>,
+    #[serde(default)]
+    ip: Option<String>,
+    #[serde(default)]
+    port: Option<String>,
+    #[serde(default)]
+    protocol: Option<ProxyProtocol>,
+    #[serde(default)]
+    strategy: ProxyStrategy,
+    #[serde(default)]
+    next_index: usize,
+    #[serde(default)]
+    health_check_enabled: bool,
+    #[serde(default)]
+    health_check_host: String,
+    #[serde(default)]
+    health_check_port: String,
+    #[serde(default = "default_max_restarts")]
+    max_restarts: u32,
+    #[serde(default = "default_bypass")]
+    bypass: String,
+}
+
+impl From<ProxyProfileOnDisk> for ProxyProfile {
+    fn from(raw: ProxyProfileOnDisk) -> Self {
+        let endpoints = if raw.endpoints.is_empty() {
+            match (raw.ip, raw.port, raw.protocol) {
+                (Some(ip), Some(port), Some(protocol)) => vec![ProxyEndpoint { ip, port, protocol }],
+                _ => Vec::new(),
+            }
+        } else {
+            raw.endpoints
+        };
+
+        Self {
+            name: raw.name,
+            endpoints,
+            strategy: raw.strategy,
+            next_index: raw.next_index,
+            health_check_enabled: raw.health_check_enabled,
+            health_check_host: raw.health_check_host,
+            health_check_port: raw.health_check_port,
+            max_restarts: raw.max_restarts,
+            bypass: raw.bypass,
+        }
+    }
+}
+
+fn default_max_restarts() -> u32 {
+    3
+}
+
+fn default_bypass() -> String {
+    "localhost,127.0.0.1".to_string()
+}
+
 #[derive(Default, Serialize, Deserialize)]
 struct AppConfig {
     profiles: Vec<ProxyProfile>,
+    #[cfg(target_os = "windows")]
+    #[serde(default)]
+    previous_system_proxy: Option<system_proxy::SystemProxySettings>,
 }
 
 #[derive(Clone)]
@@ -114,10 +273,136 @@ impl ProcessInfo {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HealthState {
+    Waiting,
+    Ready,
+    TimedOut,
+}
+
+/// 启动后健康检查：后台线程反复探测 `host:port`，通过 `Arc<Mutex<_>>` 把
+/// 就绪状态汇报给 UI 线程，这样 `update` 不需要阻塞在 TCP 连接上。
+struct HealthMonitor {
+    host: String,
+    port: u16,
+    max_restarts: u32,
+    restarts_done: u32,
+    state: Arc<Mutex<HealthState>>,
+}
+
+impl HealthMonitor {
+    fn spawn(host: String, port: u16, max_restarts: u32) -> Self {
+        let state = Arc::new(Mutex::new(HealthState::Waiting));
+        let probe_state = state.clone();
+        let probe_host = host.clone();
+        std::thread::spawn(move || {
+            let deadline = Instant::now() + HEALTH_WAIT_TIMEOUT;
+            loop {
+                if probe_once(&probe_host, port) {
+                    *probe_state.lock().unwrap() = HealthState::Ready;
+                    return;
+                }
+                if Instant::now() >= deadline {
+                    *probe_state.lock().unwrap() = HealthState::TimedOut;
+                    return;
+                }
+                std::thread::sleep(HEALTH_PROBE_INTERVAL);
+            }
+        });
+
+        Self {
+            host,
+            port,
+            max_restarts,
+            restarts_done: 0,
+            state,
+        }
+    }
+
+    fn state(&self) -> HealthState {
+        *self.state.lock().unwrap()
+    }
+
+    fn status_text(&self) -> &'static str {
+        match self.state() {
+            HealthState::Waiting => "等待服务就绪…",
+            HealthState::Ready => "服务已就绪",
+            HealthState::TimedOut => "健康检查超时",
+        }
+    }
+}
+
+fn probe_once(host: &str, port: u16) -> bool {
+    let Ok(addrs) = (host, port).to_socket_addrs() else {
+        return false;
+    };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, HEALTH_PROBE_TIMEOUT).is_ok())
+}
+
+/// 重新启动一个已退出的健康检查子进程所需的全部信息。
+struct RelaunchSpec {
+    exe_path: PathBuf,
+    args: Vec<String>,
+    proxy_url: String,
+    bypass: String,
+}
+
+fn spawn_proxied_child(
+    exe_path: &Path,
+    args: &[String],
+    proxy: &str,
+    bypass: &str,
+) -> std::io::Result<Child> {
+    let mut command = Command::new(exe_path);
+    command.args(args);
+    command
+        .env("HTTP_PROXY", proxy)
+        .env("HTTPS_PROXY", proxy)
+        .env("ALL_PROXY", proxy)
+        .env("http_proxy", proxy)
+        .env("https_proxy", proxy)
+        .env("all_proxy", proxy)
+        .env("NO_PROXY", bypass)
+        .env("no_proxy", bypass);
+
+    if let Some(parent) = exe_path.parent() {
+        command.current_dir(parent);
+    }
+
+    command.spawn()
+}
+
+/// 一个通过「使用代理启动选中进程」启动的子进程，连同启动它时用到的信息。
+///
+/// 保留 `Child` 句柄是为了能在应用退出时把它杀掉、回收，避免孤儿进程。
+struct LaunchedChild {
+    child: Child,
+    proxy_url: String,
+    profile_name: String,
+    started_at: Instant,
+    relaunch: Option<RelaunchSpec>,
+    health: Option<HealthMonitor>,
+}
+
+impl LaunchedChild {
+    fn status_text(&mut self) -> String {
+        match self.child.try_wait() {
+            Ok(Some(exit_status)) => format!("已退出({exit_status})"),
+            Ok(None) => "运行中".to_string(),
+            Err(err) => format!("状态未知: {err}"),
+        }
+    }
+}
+
 struct ProxyLauncherApp {
-    ip: String,
-    port: String,
-    protocol: ProxyProtocol,
+    endpoints: Vec<ProxyEndpoint>,
+    strategy: ProxyStrategy,
+    next_index: usize,
+    new_endpoint_ip: String,
+    new_endpoint_port: String,
+    new_endpoint_protocol: ProxyProtocol,
     processes: Vec<ProcessInfo>,
     selected_index: Option<usize>,
     args: String,
@@ -125,15 +410,35 @@ struct ProxyLauncherApp {
     profiles: Vec<ProxyProfile>,
     selected_profile_index: Option<usize>,
     profile_name: String,
+    #[cfg(target_os = "windows")]
+    system_proxy_enabled: bool,
+    #[cfg(target_os = "windows")]
+    previous_system_proxy: Option<system_proxy::SystemProxySettings>,
+    launched: Vec<LaunchedChild>,
+    health_check_enabled: bool,
+    health_check_host: String,
+    health_check_port: String,
+    max_restarts: String,
+    bypass: String,
+    local_proxy_enabled: bool,
+    local_proxy_port: String,
+    local_proxy: Option<local_proxy::LocalProxy>,
 }
 
 impl ProxyLauncherApp {
     fn new() -> Self {
         let config = load_config();
         let mut app = Self {
-            ip: "127.0.0.1".to_string(),
-            port: "7890".to_string(),
-            protocol: ProxyProtocol::Http,
+            endpoints: vec![ProxyEndpoint {
+                ip: "127.0.0.1".to_string(),
+                port: "7890".to_string(),
+                protocol: ProxyProtocol::Http,
+            }],
+            strategy: ProxyStrategy::RoundRobin,
+            next_index: 0,
+            new_endpoint_ip: "127.0.0.1".to_string(),
+            new_endpoint_port: "7890".to_string(),
+            new_endpoint_protocol: ProxyProtocol::Http,
             processes: Vec::new(),
             selected_index: None,
             args: String::new(),
@@ -141,11 +446,42 @@ impl ProxyLauncherApp {
             profiles: config.profiles,
             selected_profile_index: None,
             profile_name: "默认配置".to_string(),
+            #[cfg(target_os = "windows")]
+            system_proxy_enabled: false,
+            #[cfg(target_os = "windows")]
+            previous_system_proxy: config.previous_system_proxy,
+            launched: Vec::new(),
+            health_check_enabled: false,
+            health_check_host: String::new(),
+            health_check_port: String::new(),
+            max_restarts: default_max_restarts().to_string(),
+            bypass: default_bypass(),
+            local_proxy_enabled: false,
+            local_proxy_port: "8899".to_string(),
+            local_proxy: None,
         };
+        #[cfg(target_os = "windows")]
+        app.recover_system_proxy_on_startup();
         app.refresh_processes();
         app
     }
 
+    /// 如果上次退出时系统代理仍处于启用状态（比如进程崩溃，没能走到
+    /// `restore_system_proxy`），这里在启动时就把它清空，避免用户的系统一直
+    /// 卡在我们写入的代理配置上。由于 `previous_system_proxy` 里存的快照从来
+    /// 不是真实的原有设置（见 `system_proxy::query_current` 的文档），清空后就
+    /// 直接丢弃它，不再把它当成一份「以后要恢复」的有效快照持久化。
+    #[cfg(target_os = "windows")]
+    fn recover_system_proxy_on_startup(&mut self) {
+        if self.previous_system_proxy.is_none() {
+            return;
+        }
+        self.restore_system_proxy();
+        if self.previous_system_proxy.is_none() {
+            self.status = "检测到上次退出时系统代理仍处于启用状态，已在启动时清空为直连。".to_string();
+        }
+    }
+
     fn refresh_processes(&mut self) {
         let mut system = System::new_all();
         system.refresh_all();
@@ -170,34 +506,93 @@ impl ProxyLauncherApp {
         }
     }
 
-    fn current_proxy_url(&self) -> Result<String, String> {
-        if self.ip.trim().is_empty() {
-            return Err("IP 地址不能为空".to_string());
+    /// 按当前策略从 `endpoints` 中选出本次启动要用的上游端点。
+    ///
+    /// 轮询模式下会推进 `next_index`（调用方负责把新值持久化回所属配置）；
+    /// 故障转移模式下复用健康检查用的 `probe_once` 依次探测，取第一个可达的。
+    fn select_endpoint(&mut self) -> Result<ProxyEndpoint, String> {
+        if self.endpoints.is_empty() {
+            return Err("请先添加至少一个上游代理".to_string());
         }
 
-        let port = self
-            .port
-            .trim()
-            .parse::<u16>()
-            .map_err(|_| "端口号无效（1-65535）".to_string())?;
+        match self.strategy {
+            ProxyStrategy::RoundRobin => {
+                let (endpoint, next) = select_round_robin(&self.endpoints, self.next_index);
+                self.next_index = next;
+                self.persist_next_index();
+                Ok(endpoint)
+            }
+            ProxyStrategy::Failover => select_failover(&self.endpoints, probe_once)
+                .ok_or_else(|| "没有可用的上游代理（全部探测失败）".to_string()),
+        }
+    }
 
-        Ok(format!(
-            "{}://{}:{}",
-            self.protocol.as_scheme(),
-            self.ip.trim(),
-            port
-        ))
+    /// 若当前表单对应一个已保存的配置，把轮询游标写回去并落盘，
+    /// 这样下次启动（甚至重启程序后）仍从正确的位置继续轮询。
+    fn persist_next_index(&mut self) {
+        let Some(idx) = self.selected_profile_index else {
+            return;
+        };
+        if let Some(profile) = self.profiles.get_mut(idx) {
+            profile.next_index = self.next_index;
+        } else {
+            return;
+        }
+        let _ = save_config(&self.snapshot_config());
     }
 
-    fn launch_with_proxy(&mut self) {
-        let proxy = match self.current_proxy_url() {
-            Ok(proxy) => proxy,
+    /// 在本地端口上起一个转发代理，把当前选出的上游包起来，这样启动的进程
+    /// 实际连接的是 `127.0.0.1:<port>`，每一条连接都会被记录下来。
+    fn start_local_proxy(&mut self) {
+        let endpoint = match self.select_endpoint() {
+            Ok(endpoint) => endpoint,
             Err(err) => {
                 self.status = err;
+                self.local_proxy_enabled = false;
+                return;
+            }
+        };
+        let port: u16 = match self.local_proxy_port.trim().parse() {
+            Ok(port) => port,
+            Err(_) => {
+                self.status = "本地端口无效（1-65535）".to_string();
+                self.local_proxy_enabled = false;
                 return;
             }
         };
 
+        match local_proxy::LocalProxy::spawn(port, endpoint) {
+            Ok(local) => {
+                self.status = format!("本地转发代理已在 127.0.0.1:{port} 启动");
+                self.local_proxy = Some(local);
+            }
+            Err(err) => {
+                self.status = format!("启动本地转发代理失败: {err}");
+                self.local_proxy_enabled = false;
+            }
+        }
+    }
+
+    fn launch_with_proxy(&mut self) {
+        let proxy = if let Some(local) = &self.local_proxy {
+            format!("http://127.0.0.1:{}", local.port)
+        } else {
+            let endpoint = match self.select_endpoint() {
+                Ok(endpoint) => endpoint,
+                Err(err) => {
+                    self.status = err;
+                    return;
+                }
+            };
+            match endpoint.url() {
+                Ok(proxy) => proxy,
+                Err(err) => {
+                    self.status = err;
+                    return;
+                }
+            }
+        };
+
         let selected = match self
             .selected_index
             .and_then(|idx| self.processes.get(idx))
@@ -219,23 +614,9 @@ impl ProxyLauncherApp {
         };
 
         let args = split_args(self.args.trim());
-        let mut command = Command::new(&exe_path);
-        command.args(args);
-        command
-            .env("HTTP_PROXY", &proxy)
-            .env("HTTPS_PROXY", &proxy)
-            .env("ALL_PROXY", &proxy)
-            .env("http_proxy", &proxy)
-            .env("https_proxy", &proxy)
-            .env("all_proxy", &proxy)
-            .env("NO_PROXY", "")
-            .env("no_proxy", "");
-
-        if let Some(parent) = exe_path.parent() {
-            command.current_dir(parent);
-        }
-
-        match command.spawn() {
+        let bypass = self.bypass.trim().to_string();
+
+        match spawn_proxied_child(&exe_path, &args, &proxy, &bypass) {
             Ok(child) => {
                 self.status = format!(
                     "已启动 [{}] pid={}，代理={}。注意：仅新启动进程会继承代理环境变量。",
@@ -243,6 +624,27 @@ impl ProxyLauncherApp {
                     child.id(),
                     proxy
                 );
+
+                let health = self.start_health_check_if_enabled();
+                let relaunch = if health.is_some() {
+                    Some(RelaunchSpec {
+                        exe_path,
+                        args,
+                        proxy_url: proxy.clone(),
+                        bypass,
+                    })
+                } else {
+                    None
+                };
+
+                self.launched.push(LaunchedChild {
+                    child,
+                    proxy_url: proxy,
+                    profile_name: self.profile_name.trim().to_string(),
+                    started_at: Instant::now(),
+                    relaunch,
+                    health,
+                });
             }
             Err(err) => {
                 self.status = format!("启动失败: {err}");
@@ -250,6 +652,154 @@ impl ProxyLauncherApp {
         }
     }
 
+    fn start_health_check_if_enabled(&self) -> Option<HealthMonitor> {
+        if !self.health_check_enabled {
+            return None;
+        }
+
+        let host = if self.health_check_host.trim().is_empty() {
+            "127.0.0.1".to_string()
+        } else {
+            self.health_check_host.trim().to_string()
+        };
+        let port: u16 = self.health_check_port.trim().parse().ok()?;
+        let max_restarts: u32 = self.max_restarts.trim().parse().unwrap_or(0);
+
+        Some(HealthMonitor::spawn(host, port, max_restarts))
+    }
+
+    /// 每帧调用一次：把异常退出、配置了健康检查且仍有重启预算的子进程自动重启。
+    fn tick_launched(&mut self) {
+        for idx in 0..self.launched.len() {
+            let exit_status = match self.launched[idx].child.try_wait() {
+                Ok(Some(status)) => status,
+                _ => continue,
+            };
+            if exit_status.success() {
+                // 正常退出（包括用户主动结束）不是“异常退出”，不应该触发自动重启。
+                continue;
+            }
+
+            let Some(relaunch) = self.launched[idx].relaunch.as_ref() else {
+                continue;
+            };
+            let (can_restart, max_restarts) = match &self.launched[idx].health {
+                Some(health) => (health.restarts_done < health.max_restarts, health.max_restarts),
+                None => (false, 0),
+            };
+            if !can_restart {
+                continue;
+            }
+
+            let exe_path = relaunch.exe_path.clone();
+            let args = relaunch.args.clone();
+            let proxy_url = relaunch.proxy_url.clone();
+            let bypass = relaunch.bypass.clone();
+
+            match spawn_proxied_child(&exe_path, &args, &proxy_url, &bypass) {
+                Ok(child) => {
+                    let profile_name = self.launched[idx].profile_name.clone();
+                    let host = self.launched[idx]
+                        .health
+                        .as_ref()
+                        .map(|h| h.host.clone())
+                        .unwrap_or_default();
+                    let port = self.launched[idx].health.as_ref().map(|h| h.port).unwrap_or(0);
+                    let restarts_done = self
+                        .launched[idx]
+                        .health
+                        .as_ref()
+                        .map(|h| h.restarts_done + 1)
+                        .unwrap_or(0);
+
+                    let mut monitor = HealthMonitor::spawn(host, port, max_restarts);
+                    monitor.restarts_done = restarts_done;
+
+                    self.launched[idx].child = child;
+                    self.launched[idx].started_at = Instant::now();
+                    self.launched[idx].health = Some(monitor);
+                    self.status = format!("[{profile_name}] 异常退出，已自动重启（第 {restarts_done} 次）");
+                }
+                Err(err) => {
+                    self.status = format!("自动重启失败: {err}");
+                }
+            }
+        }
+    }
+
+    fn kill_launched(&mut self, idx: usize) {
+        if idx >= self.launched.len() {
+            return;
+        }
+        let launched = &mut self.launched[idx];
+        let _ = launched.child.kill();
+        let _ = launched.child.wait();
+        self.launched.remove(idx);
+    }
+
+    fn reap_all_launched(&mut self) {
+        for launched in &mut self.launched {
+            if matches!(launched.child.try_wait(), Ok(None)) {
+                let _ = launched.child.kill();
+            }
+            let _ = launched.child.wait();
+        }
+        self.launched.clear();
+    }
+
+    #[cfg(target_os = "windows")]
+    fn apply_system_proxy(&mut self) {
+        let Some(first) = self.endpoints.first() else {
+            self.status = "请先添加至少一个上游代理".to_string();
+            self.system_proxy_enabled = false;
+            return;
+        };
+        let ip = first.ip.trim().to_string();
+        let port = first.port.trim().to_string();
+        if ip.is_empty() || port.parse::<u16>().is_err() {
+            self.status = "端口号无效（1-65535），无法设置系统代理".to_string();
+            self.system_proxy_enabled = false;
+            return;
+        }
+
+        if self.previous_system_proxy.is_none() {
+            self.previous_system_proxy = Some(system_proxy::query_current());
+        }
+
+        match system_proxy::enable(&ip, &port, &self.bypass) {
+            Ok(()) => {
+                self.status = "已设置系统代理".to_string();
+                if let Err(err) = save_config(&self.snapshot_config()) {
+                    self.status = format!("系统代理已设置，但保存快照失败: {err}");
+                }
+            }
+            Err(err) => {
+                self.status = format!("设置系统代理失败: {err}");
+                self.system_proxy_enabled = false;
+            }
+        }
+    }
+
+    /// 注意：目前并不会真的还原用户原有的系统代理设置（见 `system_proxy::query_current`
+    /// 的文档），而是把系统代理强制清空为直连。这里的状态提示和按钮文案必须
+    /// 如实反映这一点，不能让用户误以为这是无损恢复。
+    #[cfg(target_os = "windows")]
+    fn restore_system_proxy(&mut self) {
+        match system_proxy::disable(self.previous_system_proxy.as_ref()) {
+            Ok(()) => {
+                self.status = "已将系统代理清空为直连（注意：这不是还原您原有的代理设置）".to_string();
+                self.previous_system_proxy = None;
+                self.system_proxy_enabled = false;
+                if let Err(err) = save_config(&self.snapshot_config()) {
+                    self.status = format!("已清空系统代理，但保存快照失败: {err}");
+                }
+            }
+            Err(err) => {
+                self.status = format!("清空系统代理失败: {err}");
+            }
+        }
+    }
+
     fn save_new_profile(&mut self) {
         if self.profile_name.trim().is_empty() {
             self.status = "配置名称不能为空".to_string();
@@ -258,16 +808,19 @@ impl ProxyLauncherApp {
 
         let profile = ProxyProfile {
             name: self.profile_name.trim().to_string(),
-            ip: self.ip.trim().to_string(),
-            port: self.port.trim().to_string(),
-            protocol: self.protocol,
+            endpoints: self.endpoints.clone(),
+            strategy: self.strategy,
+            next_index: self.next_index,
+            health_check_enabled: self.health_check_enabled,
+            health_check_host: self.health_check_host.trim().to_string(),
+            health_check_port: self.health_check_port.trim().to_string(),
+            max_restarts: self.max_restarts.trim().parse().unwrap_or_else(|_| default_max_restarts()),
+            bypass: self.bypass.trim().to_string(),
         };
 
         self.profiles.push(profile);
         self.selected_profile_index = Some(self.profiles.len() - 1);
-        if let Err(err) = save_config(&AppConfig {
-            profiles: self.profiles.clone(),
-        }) {
+        if let Err(err) = save_config(&self.snapshot_config()) {
             self.status = format!("保存配置失败: {err}");
             return;
         }
@@ -286,14 +839,17 @@ impl ProxyLauncherApp {
 
         if let Some(profile) = self.profiles.get_mut(idx) {
             profile.name = self.profile_name.trim().to_string();
-            profile.ip = self.ip.trim().to_string();
-            profile.port = self.port.trim().to_string();
-            profile.protocol = self.protocol;
+            profile.endpoints = self.endpoints.clone();
+            profile.strategy = self.strategy;
+            profile.next_index = self.next_index;
+            profile.health_check_enabled = self.health_check_enabled;
+            profile.health_check_host = self.health_check_host.trim().to_string();
+            profile.health_check_port = self.health_check_port.trim().to_string();
+            profile.max_restarts = self.max_restarts.trim().parse().unwrap_or_else(|_| default_max_restarts());
+            profile.bypass = self.bypass.trim().to_string();
         }
 
-        if let Err(err) = save_config(&AppConfig {
-            profiles: self.profiles.clone(),
-        }) {
+        if let Err(err) = save_config(&self.snapshot_config()) {
             self.status = format!("修改配置失败: {err}");
             return;
         }
@@ -316,9 +872,7 @@ impl ProxyLauncherApp {
 
         self.selected_profile_index = None;
 
-        if let Err(err) = save_config(&AppConfig {
-            profiles: self.profiles.clone(),
-        }) {
+        if let Err(err) = save_config(&self.snapshot_config()) {
             self.status = format!("删除配置失败: {err}");
             return;
         }
@@ -326,6 +880,14 @@ impl ProxyLauncherApp {
         self.status = "删除配置成功".to_string();
     }
 
+    fn snapshot_config(&self) -> AppConfig {
+        AppConfig {
+            profiles: self.profiles.clone(),
+            #[cfg(target_os = "windows")]
+            previous_system_proxy: self.previous_system_proxy.clone(),
+        }
+    }
+
     fn load_selected_profile_to_form(&mut self) {
         let idx = match self.selected_profile_index {
             Some(i) => i,
@@ -337,9 +899,14 @@ impl ProxyLauncherApp {
 
         if let Some(profile) = self.profiles.get(idx) {
             self.profile_name = profile.name.clone();
-            self.ip = profile.ip.clone();
-            self.port = profile.port.clone();
-            self.protocol = profile.protocol;
+            self.endpoints = profile.endpoints.clone();
+            self.strategy = profile.strategy;
+            self.next_index = profile.next_index;
+            self.health_check_enabled = profile.health_check_enabled;
+            self.health_check_host = profile.health_check_host.clone();
+            self.health_check_port = profile.health_check_port.clone();
+            self.max_restarts = profile.max_restarts.to_string();
+            self.bypass = profile.bypass.clone();
             self.status = "已加载配置到当前输入框".to_string();
         }
     }
@@ -347,6 +914,14 @@ impl ProxyLauncherApp {
 
 impl eframe::App for ProxyLauncherApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if !self.launched.is_empty() {
+            self.tick_launched();
+            ctx.request_repaint();
+        }
+        if self.local_proxy.is_some() {
+            ctx.request_repaint();
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("rproxy - 进程代理启动器");
             ui.label("通过设置代理环境变量启动目标进程（HTTP/HTTPS/ALL_PROXY）。");
@@ -394,27 +969,151 @@ impl eframe::App for ProxyLauncherApp {
 
             ui.separator();
 
+            ui.group(|ui| {
+                ui.label("上游代理列表（按顺序使用）");
+                let mut to_remove = None;
+                let mut to_move_up = None;
+                for (idx, endpoint) in self.endpoints.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{}. {} {}:{}",
+                            idx + 1,
+                            endpoint.protocol.label(),
+                            endpoint.ip,
+                            endpoint.port
+                        ));
+                        if idx > 0 && ui.button("上移").clicked() {
+                            to_move_up = Some(idx);
+                        }
+                        if ui.button("删除").clicked() {
+                            to_remove = Some(idx);
+                        }
+                    });
+                }
+                if let Some(idx) = to_move_up {
+                    self.endpoints.swap(idx, idx - 1);
+                }
+                if let Some(idx) = to_remove {
+                    self.endpoints.remove(idx);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("新增端点 IP:");
+                    ui.text_edit_singleline(&mut self.new_endpoint_ip);
+                    ui.label("端口:");
+                    ui.text_edit_singleline(&mut self.new_endpoint_port);
+                    egui::ComboBox::from_label("协议")
+                        .selected_text(self.new_endpoint_protocol.label())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.new_endpoint_protocol, ProxyProtocol::Http, "HTTP");
+                            ui.selectable_value(&mut self.new_endpoint_protocol, ProxyProtocol::Socks5, "SOCKS5");
+                            ui.selectable_value(&mut self.new_endpoint_protocol, ProxyProtocol::Socks4, "SOCKS4");
+                        });
+                    if ui.button("添加端点").clicked() {
+                        self.endpoints.push(ProxyEndpoint {
+                            ip: self.new_endpoint_ip.trim().to_string(),
+                            port: self.new_endpoint_port.trim().to_string(),
+                            protocol: self.new_endpoint_protocol,
+                        });
+                    }
+                });
+
+                egui::ComboBox::from_label("选择策略")
+                    .selected_text(self.strategy.label())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.strategy, ProxyStrategy::RoundRobin, "轮询");
+                        ui.selectable_value(&mut self.strategy, ProxyStrategy::Failover, "故障转移");
+                    });
+            });
+
+            ui.group(|ui| {
+                ui.checkbox(&mut self.health_check_enabled, "启动后健康检查");
+                ui.horizontal(|ui| {
+                    ui.label("检查地址:");
+                    ui.text_edit_singleline(&mut self.health_check_host);
+                    ui.label("端口:");
+                    ui.text_edit_singleline(&mut self.health_check_port);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("最大自动重启次数:");
+                    ui.text_edit_singleline(&mut self.max_restarts);
+                });
+            });
+
             ui.horizontal(|ui| {
-                ui.label("代理 IP:");
-                ui.text_edit_singleline(&mut self.ip);
-                ui.label("端口:");
-                ui.text_edit_singleline(&mut self.port);
+                ui.label("绕过代理（NO_PROXY，逗号分隔）:");
+                ui.text_edit_singleline(&mut self.bypass);
             });
 
-            egui::ComboBox::from_label("代理协议")
-                .selected_text(self.protocol.label())
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.protocol, ProxyProtocol::Http, "HTTP");
-                    ui.selectable_value(&mut self.protocol, ProxyProtocol::Socks5, "SOCKS5");
-                    ui.selectable_value(&mut self.protocol, ProxyProtocol::Socks4, "SOCKS4");
+            ui.group(|ui| {
+                ui.label("本地转发代理 / 流量监控");
+                ui.horizontal(|ui| {
+                    ui.label("本地端口:");
+                    ui.text_edit_singleline(&mut self.local_proxy_port);
+                    let resp = ui.checkbox(
+                        &mut self.local_proxy_enabled,
+                        "启用（启动进程时改用 127.0.0.1:<本地端口>）",
+                    );
+                    if resp.changed() {
+                        if self.local_proxy_enabled {
+                            self.start_local_proxy();
+                        } else {
+                            self.local_proxy = None;
+                        }
+                    }
                 });
 
+                if let Some(local) = &self.local_proxy {
+                    egui::ScrollArea::vertical()
+                        .id_salt("traffic_log")
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("traffic_log_grid").striped(true).show(ui, |ui| {
+                                ui.label("发生于");
+                                ui.label("客户端");
+                                ui.label("目标");
+                                ui.label("上行");
+                                ui.label("下行");
+                                ui.label("耗时");
+                                ui.end_row();
+                                for entry in local.recent_entries() {
+                                    ui.label(format!("{:.0}s 前", entry.started_at.elapsed().as_secs_f32()));
+                                    ui.label(&entry.client_addr);
+                                    ui.label(&entry.target);
+                                    ui.label(format!("{}B", entry.bytes_up));
+                                    ui.label(format!("{}B", entry.bytes_down));
+                                    ui.label(format!("{:.1}s", entry.duration.as_secs_f32()));
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                }
+            });
+
             ui.horizontal(|ui| {
                 if ui.button("刷新进程列表").clicked() {
                     self.refresh_processes();
                 }
-                if let Ok(proxy) = self.current_proxy_url() {
-                    ui.label(format!("当前代理: {proxy}"));
+                if let Some(first) = self.endpoints.first().and_then(|e| e.url().ok()) {
+                    ui.label(format!("下一次可能使用: {first}（共 {} 个端点）", self.endpoints.len()));
+                }
+            });
+
+            #[cfg(target_os = "windows")]
+            ui.horizontal(|ui| {
+                let resp = ui.checkbox(&mut self.system_proxy_enabled, "同时设置系统代理");
+                if resp.changed() {
+                    if self.system_proxy_enabled {
+                        self.apply_system_proxy();
+                    } else {
+                        self.restore_system_proxy();
+                    }
+                }
+                if ui
+                    .button("清空系统代理（注意：非无损恢复，会强制改为直连）")
+                    .clicked()
+                {
+                    self.restore_system_proxy();
                 }
             });
 
@@ -441,10 +1140,46 @@ impl eframe::App for ProxyLauncherApp {
                 self.launch_with_proxy();
             }
 
+            ui.separator();
+            ui.group(|ui| {
+                ui.label("已启动进程");
+                let mut to_kill = None;
+                egui::ScrollArea::vertical()
+                    .id_salt("launched_processes")
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for (idx, launched) in self.launched.iter_mut().enumerate() {
+                            let status = launched.status_text();
+                            let health = launched.health.as_ref().map(|h| h.status_text());
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "[{}] pid={} 代理={} 状态={} 已运行={:.0}s{}",
+                                    launched.profile_name,
+                                    launched.child.id(),
+                                    launched.proxy_url,
+                                    status,
+                                    launched.started_at.elapsed().as_secs_f32(),
+                                    health.map(|h| format!(" 健康检查={h}")).unwrap_or_default()
+                                ));
+                                if ui.button("结束").clicked() {
+                                    to_kill = Some(idx);
+                                }
+                            });
+                        }
+                    });
+                if let Some(idx) = to_kill {
+                    self.kill_launched(idx);
+                }
+            });
+
             ui.separator();
             ui.label(format!("状态: {}", self.status));
         });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.reap_all_launched();
+    }
 }
 
 fn split_args(input: &str) -> Vec<String> {
@@ -485,7 +1220,10 @@ fn save_config(config: &AppConfig) -> Result<(), String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{split_args, AppConfig, ProxyProfile, ProxyProtocol};
+    use super::{
+        default_bypass, default_max_restarts, select_failover, select_round_robin, split_args,
+        AppConfig, ProxyEndpoint, ProxyProfile, ProxyProtocol, ProxyStrategy,
+    };
 
     #[test]
     fn parse_args() {
@@ -500,20 +1238,79 @@ mod tests {
         assert_eq!(ProxyProtocol::Socks4.as_scheme(), "socks4");
     }
 
+    fn endpoint(ip: &str, port: &str) -> ProxyEndpoint {
+        ProxyEndpoint {
+            ip: ip.to_string(),
+            port: port.to_string(),
+            protocol: ProxyProtocol::Http,
+        }
+    }
+
     #[test]
     fn config_roundtrip() {
         let cfg = AppConfig {
             profiles: vec![ProxyProfile {
                 name: "办公室代理".to_string(),
-                ip: "10.10.10.1".to_string(),
-                port: "8080".to_string(),
-                protocol: ProxyProtocol::Http,
+                endpoints: vec![endpoint("10.10.10.1", "8080")],
+                strategy: ProxyStrategy::RoundRobin,
+                next_index: 0,
+                health_check_enabled: false,
+                health_check_host: String::new(),
+                health_check_port: String::new(),
+                max_restarts: default_max_restarts(),
+                bypass: default_bypass(),
             }],
+            #[cfg(target_os = "windows")]
+            previous_system_proxy: None,
         };
 
         let json = serde_json::to_string(&cfg).expect("serialize config");
         let parsed: AppConfig = serde_json::from_str(&json).expect("deserialize config");
         assert_eq!(parsed.profiles.len(), 1);
         assert_eq!(parsed.profiles[0].name, "办公室代理");
+        assert_eq!(parsed.profiles[0].endpoints.len(), 1);
+    }
+
+    #[test]
+    fn legacy_single_endpoint_profile_deserializes() {
+        let legacy = r#"{
+            "name": "旧配置",
+            "ip": "192.168.1.1",
+            "port": "1080",
+            "protocol": "Socks5",
+            "max_restarts": 2
+        }"#;
+
+        let profile: ProxyProfile = serde_json::from_str(legacy).expect("deserialize legacy profile");
+        assert_eq!(profile.endpoints.len(), 1);
+        assert_eq!(profile.endpoints[0].ip, "192.168.1.1");
+        assert_eq!(profile.endpoints[0].port, "1080");
+        assert_eq!(profile.bypass, default_bypass());
+    }
+
+    #[test]
+    fn round_robin_wraps_around() {
+        let endpoints = vec![endpoint("1.1.1.1", "80"), endpoint("2.2.2.2", "80")];
+
+        let (first, cursor) = select_round_robin(&endpoints, 0);
+        assert_eq!(first.ip, "1.1.1.1");
+        let (second, cursor) = select_round_robin(&endpoints, cursor);
+        assert_eq!(second.ip, "2.2.2.2");
+        let (third, _) = select_round_robin(&endpoints, cursor);
+        assert_eq!(third.ip, "1.1.1.1");
+    }
+
+    #[test]
+    fn failover_picks_first_reachable() {
+        let endpoints = vec![endpoint("1.1.1.1", "80"), endpoint("2.2.2.2", "80")];
+
+        let picked = select_failover(&endpoints, |ip, _port| ip == "2.2.2.2");
+        assert_eq!(picked.map(|e| e.ip), Some("2.2.2.2".to_string()));
+    }
+
+    #[test]
+    fn failover_none_reachable() {
+        let endpoints = vec![endpoint("1.1.1.1", "80")];
+        assert!(select_failover(&endpoints, |_, _| false).is_none());
     }
 }
@@ -0,0 +1,141 @@
+//! Windows 系统级代理设置（WinInet 每连接选项）。
+//!
+//! `launch_with_proxy` 只会把代理环境变量注入新启动的子进程，很多原生 Windows
+//! 程序（尤其是不读环境变量、而是读系统代理设置的程序）并不会受益。这个模块
+//! 通过 `InternetSetOptionW` 写入/清除 IE/WinInet 的“每连接”代理选项，让整个
+//! 系统都走配置的代理，并在调用方请求时把系统代理清空为直连（不会还原
+//! 修改前的原有设置，见 `query_current`）。
+#![cfg(target_os = "windows")]
+
+use serde::{Deserialize, Serialize};
+use std::mem::size_of;
+use windows_sys::Win32::Networking::WinInet::{
+    InternetSetOptionW, INTERNET_OPTION_PER_CONNECTION_OPTION, INTERNET_OPTION_PROXY_SETTINGS_CHANGED,
+    INTERNET_OPTION_REFRESH, INTERNET_PER_CONN_FLAGS, INTERNET_PER_CONN_OPTIONW,
+    INTERNET_PER_CONN_OPTION_LISTW, INTERNET_PER_CONN_OPTION_UNION, INTERNET_PER_CONN_PROXY_BYPASS,
+    INTERNET_PER_CONN_PROXY_SERVER, PROXY_TYPE_AUTO_DETECT, PROXY_TYPE_DIRECT, PROXY_TYPE_PROXY,
+};
+
+/// 应用代理前的系统设置快照，用于“恢复系统代理”。
+///
+/// 持久化进 `AppConfig`，这样即便程序在代理生效期间崩溃，下次启动时依然能
+/// 发现系统代理仍处于启用状态并将其清空，而不会把系统卡在我们写入的代理配置上。
+///
+/// 注意：`query_current` 目前并不会真的读取用户原有的系统代理设置（见其
+/// 文档），所以这份快照实际保存的始终是“直连”，恢复时会把系统代理强制
+/// 清空，而不是还原到用户设置我们之前的状态。
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SystemProxySettings {
+    flags: u32,
+    proxy_server: String,
+    proxy_bypass: String,
+}
+
+/// 将系统代理设置为 `ip:port`，三种协议前缀都指向同一个地址。
+///
+/// `bypass` 为空时使用 WinInet 默认的 `"<local>"`。
+pub fn enable(ip: &str, port: &str, bypass: &str) -> Result<(), String> {
+    let proxy_server = format!("http={ip}:{port};https={ip}:{port};socks={ip}:{port}");
+    apply(PROXY_TYPE_PROXY | PROXY_TYPE_DIRECT, &proxy_server, &format_bypass(bypass))
+}
+
+/// 把应用内统一使用的逗号分隔 `NO_PROXY` 列表，转换成 WinInet 期望的
+/// 分号分隔格式，并确保其中包含 `<local>`（否则本地地址不会被正确豁免）。
+fn format_bypass(bypass: &str) -> String {
+    let mut entries: Vec<&str> = bypass.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+    if !entries.iter().any(|entry| entry.eq_ignore_ascii_case("<local>")) {
+        entries.push("<local>");
+    }
+    entries.join(";")
+}
+
+/// 恢复之前保存的系统代理设置；如果没有保存过快照，则退回到直连+自动检测。
+///
+/// 由于 `query_current` 并不读取真实的原有设置（见其文档），这里实际上总是
+/// 把系统代理清空为直连，而不是还原用户之前配置的代理（例如公司代理）。
+/// 调用方（GUI）必须把这一点明确告知用户，不能把它当作无损的“恢复”。
+pub fn disable(previous: Option<&SystemProxySettings>) -> Result<(), String> {
+    match previous {
+        Some(settings) => apply(settings.flags, &settings.proxy_server, &settings.proxy_bypass),
+        None => apply(PROXY_TYPE_DIRECT | PROXY_TYPE_AUTO_DETECT, "", "<local>"),
+    }
+}
+
+/// 供启用前保存快照，名义上用于之后的“恢复系统代理”。
+///
+/// WinInet 没有提供一个可以安全复用的“读当前每连接选项”接口（`InternetQueryOptionW`
+/// 对 `INTERNET_OPTION_PER_CONNECTION_OPTION` 返回的字符串需要调用方用
+/// `GlobalFree` 释放，且不同 Windows 版本下的行为并不总是一致），为了不在没有
+/// Windows 环境可供验证的情况下引入读取/释放系统内存的 unsafe 代码，这里明确
+/// 选择不去读真实设置：固定返回“直连”快照。
+///
+/// 这意味着“恢复系统代理”是破坏性的——它会把系统代理强制设为直连，而不是
+/// 真正还原用户原有的代理配置（比如公司代理）。调用方必须在 UI 上明确提示
+/// 这一点，不要把它包装成无损操作。
+pub fn query_current() -> SystemProxySettings {
+    SystemProxySettings {
+        flags: PROXY_TYPE_DIRECT,
+        proxy_server: String::new(),
+        proxy_bypass: "<local>".to_string(),
+    }
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn apply(flags: u32, proxy_server: &str, proxy_bypass: &str) -> Result<(), String> {
+    let mut proxy_server_w = to_wide(proxy_server);
+    let mut proxy_bypass_w = to_wide(proxy_bypass);
+
+    let mut options = [
+        INTERNET_PER_CONN_OPTIONW {
+            dwOption: INTERNET_PER_CONN_FLAGS,
+            Value: INTERNET_PER_CONN_OPTION_UNION { dwValue: flags },
+        },
+        INTERNET_PER_CONN_OPTIONW {
+            dwOption: INTERNET_PER_CONN_PROXY_SERVER,
+            Value: INTERNET_PER_CONN_OPTION_UNION {
+                pszValue: proxy_server_w.as_mut_ptr(),
+            },
+        },
+        INTERNET_PER_CONN_OPTIONW {
+            dwOption: INTERNET_PER_CONN_PROXY_BYPASS,
+            Value: INTERNET_PER_CONN_OPTION_UNION {
+                pszValue: proxy_bypass_w.as_mut_ptr(),
+            },
+        },
+    ];
+
+    let mut list = INTERNET_PER_CONN_OPTION_LISTW {
+        dwSize: size_of::<INTERNET_PER_CONN_OPTION_LISTW>() as u32,
+        pszConnection: std::ptr::null_mut(),
+        dwOptionCount: options.len() as u32,
+        dwOptionError: 0,
+        pOptions: options.as_mut_ptr(),
+    };
+
+    let ok = unsafe {
+        InternetSetOptionW(
+            std::ptr::null_mut(),
+            INTERNET_OPTION_PER_CONNECTION_OPTION,
+            &mut list as *mut _ as *mut _,
+            list.dwSize,
+        )
+    };
+    if ok == 0 {
+        return Err("InternetSetOptionW(PER_CONNECTION_OPTION) 调用失败".to_string());
+    }
+
+    unsafe {
+        InternetSetOptionW(
+            std::ptr::null_mut(),
+            INTERNET_OPTION_PROXY_SETTINGS_CHANGED,
+            std::ptr::null_mut(),
+            0,
+        );
+        InternetSetOptionW(std::ptr::null_mut(), INTERNET_OPTION_REFRESH, std::ptr::null_mut(), 0);
+    }
+
+    Ok(())
+}